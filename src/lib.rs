@@ -0,0 +1,484 @@
+//! Core random string/passphrase generation, usable as a library (including from
+//! `wasm32-unknown-unknown`, with `getrandom`'s `js` backend). The CLI around this
+//! (argument parsing, clipboard, colored output) lives in `main.rs`.
+
+use getrandom::getrandom;
+
+pub const DIGITS: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+pub const LETTERS_LC: [char; 26] = [
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
+    't', 'u', 'v', 'w', 'x', 'y', 'z',
+];
+pub const LETTERS_UC: [char; 26] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+];
+pub const SEPARATORS: [char; 3] = ['-', '.', '_'];
+pub const MISC_SYMBOLS: [char; 4] = ['!', '*', '&', '#'];
+
+// easily confused when read aloud or copied off a screen, e.g. '0' vs 'O' vs 'o'
+pub const DEFAULT_AMBIGUOUS_CHARS: [char; 13] =
+    ['0', 'O', 'o', '1', 'l', 'I', '|', '5', 'S', '2', 'Z', 'B', '8'];
+
+pub const DEFINED_SETS: [&'static [char]; 5] = [
+    &DIGITS,
+    &LETTERS_LC,
+    &LETTERS_UC,
+    &SEPARATORS,
+    &MISC_SYMBOLS,
+];
+
+// bundled so `--words` works out of the box; override with `--wordlist <path>`
+pub const DEFAULT_WORDLIST: &'static str = include_str!("wordlist.txt");
+
+// A source of raw entropy bytes, abstracting over where they actually come from
+// (the OS CSPRNG, a seeded PRNG, ...) so the index-picking logic doesn't care.
+pub trait RandSource {
+    fn fill(&mut self, buf: &mut [u8]);
+}
+
+impl<R: RandSource + ?Sized> RandSource for &mut R {
+    fn fill(&mut self, buf: &mut [u8]) {
+        (**self).fill(buf)
+    }
+}
+
+/// The default, CSPRNG-backed source: every byte comes straight from the OS.
+pub struct GetRandomSource;
+
+impl RandSource for GetRandomSource {
+    fn fill(&mut self, buf: &mut [u8]) {
+        getrandom(buf).unwrap();
+    }
+}
+
+/// Wyrand: a small, fast, non-cryptographic PRNG used for `--seed`, so runs with
+/// the same seed and args reproduce byte-for-byte.
+pub struct Wyrand {
+    state: u64,
+}
+
+impl Wyrand {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0xa0761d6478bd642f);
+        let t = (self.state as u128) * ((self.state ^ 0xe7037ed1a0b428db) as u128);
+        ((t >> 64) ^ t) as u64
+    }
+}
+
+impl RandSource for Wyrand {
+    fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+// Batches raw entropy from a `RandSource` so picking many indices in a row doesn't
+// make a syscall (or PRNG step) per byte.
+struct ByteBuffer<R: RandSource> {
+    source: R,
+    buf: [u8; 256],
+    pos: usize,
+}
+
+impl<R: RandSource> ByteBuffer<R> {
+    fn new(source: R) -> Self {
+        Self {
+            source,
+            buf: [0; 256],
+            pos: 256,
+        }
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        if self.pos >= self.buf.len() {
+            self.source.fill(&mut self.buf);
+            self.pos = 0;
+        }
+        let byte = self.buf[self.pos];
+        self.pos += 1;
+        byte
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        u32::from_le_bytes([
+            self.next_byte(),
+            self.next_byte(),
+            self.next_byte(),
+            self.next_byte(),
+        ])
+    }
+}
+
+// Picks an unbiased index in `0..pool_len` via rejection sampling: draw a random
+// value in a fixed-width range, throw away the remainder that doesn't divide evenly
+// into `pool_len`, and take the rest modulo `pool_len`. This avoids the bias that
+// mapping the value directly (e.g. by rounding a scaled float) would introduce.
+fn rand_index<R: RandSource>(bytes: &mut ByteBuffer<R>, pool_len: usize) -> usize {
+    if pool_len <= 256 {
+        let zone = 256 - (256 % pool_len);
+        loop {
+            let byte = bytes.next_byte() as usize;
+            if byte < zone {
+                return byte % pool_len;
+            }
+        }
+    } else {
+        // pool is wider than a byte can address, draw a u32 instead
+        let range = 1u64 << 32;
+        let zone = range - (range % pool_len as u64);
+        loop {
+            let value = bytes.next_u32() as u64;
+            if value < zone {
+                return (value % pool_len as u64) as usize;
+            }
+        }
+    }
+}
+
+fn gen_rand_string(pool: &[char], len: usize, source: &mut (impl RandSource + ?Sized)) -> String {
+    if len == 0 || pool.is_empty() {
+        return String::new();
+    }
+
+    let mut bytes = ByteBuffer::new(source);
+    (0..len)
+        .map(|_| pool[rand_index(&mut bytes, pool.len())])
+        .collect()
+}
+
+// Same as `gen_rand_string`, but reserves one position per entry in `required_sets`
+// and fills it from that set, guaranteeing coverage. The guaranteed characters are
+// shuffled in afterwards so they don't always land at the same positions.
+fn gen_rand_string_strict(
+    pool: &[char],
+    required_sets: &[Vec<char>],
+    len: usize,
+    source: &mut (impl RandSource + ?Sized),
+) -> String {
+    if len == 0 || pool.is_empty() {
+        return String::new();
+    }
+
+    let mut bytes = ByteBuffer::new(source);
+
+    let mut chars: Vec<char> = (0..len - required_sets.len())
+        .map(|_| pool[rand_index(&mut bytes, pool.len())])
+        .collect();
+
+    for set in required_sets {
+        chars.push(set[rand_index(&mut bytes, set.len())]);
+    }
+
+    // Fisher-Yates shuffle
+    for i in (1..chars.len()).rev() {
+        let j = rand_index(&mut bytes, i + 1);
+        chars.swap(i, j);
+    }
+
+    chars.into_iter().collect()
+}
+
+/// Diceware-style passphrase: picks `n` random words from `word_pool`, joined by `sep`.
+pub fn generate_words(
+    word_pool: &[&str],
+    n: usize,
+    sep: char,
+    source: &mut (impl RandSource + ?Sized),
+) -> String {
+    if n == 0 || word_pool.is_empty() {
+        return String::new();
+    }
+
+    let mut bytes = ByteBuffer::new(source);
+    (0..n)
+        .map(|_| word_pool[rand_index(&mut bytes, word_pool.len())])
+        .collect::<Vec<_>>()
+        .join(&sep.to_string())
+}
+
+/// Builds a deduplicated character pool from pre-defined sets, custom additions and
+/// removals, and an optional "ambiguous" filter. Mirrors the CLI's `entries` grammar.
+pub struct PoolBuilder {
+    use_defined_sets: [bool; DEFINED_SETS.len()],
+    custom_sets: Vec<(bool, Vec<char>)>,
+    remove_chars: Vec<char>,
+    ambiguous_chars: Option<Vec<char>>,
+}
+
+impl Default for PoolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PoolBuilder {
+    pub fn new() -> Self {
+        Self {
+            use_defined_sets: [true; DEFINED_SETS.len()],
+            custom_sets: Vec::new(),
+            remove_chars: Vec::new(),
+            ambiguous_chars: None,
+        }
+    }
+
+    pub fn use_defined_set(&mut self, index: usize, enabled: bool) -> &mut Self {
+        self.use_defined_sets[index] = enabled;
+        self
+    }
+
+    pub fn use_all_defined_sets(&mut self, enabled: bool) -> &mut Self {
+        self.use_defined_sets = [enabled; DEFINED_SETS.len()];
+        self
+    }
+
+    pub fn add_custom_set(&mut self, chars: impl IntoIterator<Item = char>) -> &mut Self {
+        self.custom_sets.push((true, chars.into_iter().collect()));
+        self
+    }
+
+    pub fn remove_chars(&mut self, chars: impl IntoIterator<Item = char>) -> &mut Self {
+        self.remove_chars.extend(chars);
+        self
+    }
+
+    pub fn filter_ambiguous(&mut self, chars: impl IntoIterator<Item = char>) -> &mut Self {
+        self.ambiguous_chars = Some(chars.into_iter().collect());
+        self
+    }
+
+    /// Builds the flat character pool, applying adds, then removes, then the
+    /// ambiguous-character filter (in that order).
+    pub fn build(&self) -> Result<Vec<char>, String> {
+        let mut pool = Vec::new();
+        for i in 0..DEFINED_SETS.len() {
+            if self.use_defined_sets[i] {
+                pool.extend(DEFINED_SETS[i]);
+            }
+        }
+        for (added, set) in &self.custom_sets {
+            if !added {
+                continue;
+            }
+            for c in set {
+                if pool.contains(c) {
+                    return Err(format!(
+                        "can't add character to pool, already exists: '{}'",
+                        c
+                    ));
+                }
+                pool.push(*c);
+            }
+        }
+        for c in &self.remove_chars {
+            let Some(idx) = pool.iter().position(|c2| c2 == c) else {
+                return Err(format!(
+                    "can't remove character from pool, doesn't exist: '{}'",
+                    c
+                ));
+            };
+            pool.remove(idx);
+        }
+        if let Some(ambiguous) = &self.ambiguous_chars {
+            pool.retain(|c| !ambiguous.contains(c));
+        }
+        Ok(pool)
+    }
+
+    /// Builds the per-set sub-pools used by `--strict`: one per enabled defined set
+    /// plus one per added custom set, with removals and the ambiguous filter applied.
+    /// Errors if the ambiguous filter empties a set that wasn't already empty.
+    pub fn build_required_sets(&self) -> Result<Vec<Vec<char>>, String> {
+        let defined = (0..DEFINED_SETS.len())
+            .filter(|&i| self.use_defined_sets[i])
+            .map(|i| {
+                DEFINED_SETS[i]
+                    .iter()
+                    .copied()
+                    .filter(|c| !self.remove_chars.contains(c))
+                    .collect::<Vec<_>>()
+            });
+        let custom = self
+            .custom_sets
+            .iter()
+            .filter(|(added, _)| *added)
+            .map(|(_, set)| {
+                set.iter()
+                    .copied()
+                    .filter(|c| !self.remove_chars.contains(c))
+                    .collect::<Vec<_>>()
+            });
+        let mut sets: Vec<Vec<char>> =
+            defined.chain(custom).filter(|set| !set.is_empty()).collect();
+
+        if let Some(ambiguous) = &self.ambiguous_chars {
+            for set in &mut sets {
+                set.retain(|c| !ambiguous.contains(c));
+                if set.is_empty() {
+                    return Err(
+                        "a required set was emptied by the ambiguous-character filter".into(),
+                    );
+                }
+            }
+        }
+
+        Ok(sets)
+    }
+}
+
+/// Generates random strings from a character pool, with optional class-coverage
+/// (`--strict`) and a deterministic seed. Build the pool with `PoolBuilder`.
+pub struct Generator {
+    pool: Vec<char>,
+    required_sets: Vec<Vec<char>>,
+    strict: bool,
+    source: Box<dyn RandSource>,
+    pub len: usize,
+}
+
+impl Generator {
+    pub fn new(pool: Vec<char>, len: usize) -> Self {
+        Self {
+            pool,
+            required_sets: Vec::new(),
+            strict: false,
+            source: Box::new(GetRandomSource),
+            len,
+        }
+    }
+
+    /// Enables `--strict` mode with the required sub-pools from
+    /// `PoolBuilder::build_required_sets`. Errors if `len` is smaller than the
+    /// number of required sets.
+    pub fn with_strict(mut self, required_sets: Vec<Vec<char>>) -> Result<Self, String> {
+        if self.len < required_sets.len() {
+            return Err(format!(
+                "length ({}) is smaller than the number of required sets ({})",
+                self.len,
+                required_sets.len()
+            ));
+        }
+        self.strict = true;
+        self.required_sets = required_sets;
+        Ok(self)
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.source = Box::new(Wyrand::new(seed));
+        self
+    }
+
+    pub fn generate(&mut self) -> String {
+        if self.strict {
+            gen_rand_string_strict(
+                &self.pool,
+                &self.required_sets,
+                self.len,
+                self.source.as_mut(),
+            )
+        } else {
+            gen_rand_string(&self.pool, self.len, self.source.as_mut())
+        }
+    }
+
+    pub fn generate_many(&mut self, n: usize) -> Vec<String> {
+        (0..n).map(|_| self.generate()).collect()
+    }
+}
+
+// ---- UNIT TESTS ----
+
+#[cfg(test)]
+#[inline(always)]
+fn gen_rand_char(pool: &[char]) -> char {
+    // the unit test shouldn't give this an empty pool
+    assert_eq!(pool.is_empty(), false);
+
+    let mut bytes = ByteBuffer::new(GetRandomSource);
+    pool[rand_index(&mut bytes, pool.len())]
+}
+
+#[test]
+fn test() {
+    let mut source = GetRandomSource;
+
+    // --- SMALLER TESTS ---
+    assert_eq!(gen_rand_string(&[], 5, &mut source).as_str(), "");
+    assert_eq!(gen_rand_string(&[], 0, &mut source).as_str(), "");
+    assert_eq!(gen_rand_string(&['a'], 0, &mut source).as_str(), "");
+    for _ in 0..100 {
+        assert_eq!(gen_rand_string(&['a'], 1, &mut source).as_str(), "a");
+    }
+
+    // --- BIGGER TESTS ---
+    println!("\ntesting pool of size 1");
+
+    let pool = ['a'];
+    for _ in 0..10_000_000 {
+        // this asserts that gen_rand_char doesn't calculate an invalid index or char
+        let c = gen_rand_char(&pool);
+        assert_eq!(c, 'a');
+    }
+    println!("done\ntesting pool of size 2");
+
+    let pool = ['a', 'b'];
+    for _ in 0..10_000_000 {
+        let c = gen_rand_char(&pool);
+        assert!(c == 'a' || c == 'b');
+    }
+
+    println!("done\nchecking distrobution");
+
+    // assert that the distrobutions are ~even
+    let mut a_count: i32 = 0;
+    let mut b_count: i32 = 0;
+    for _ in 0..10_000_000 {
+        let c = gen_rand_char(&pool);
+        match c {
+            'a' => a_count += 1,
+            'b' => b_count += 1,
+            _ => unreachable!(),
+        }
+    }
+    let diff = (a_count - b_count).abs();
+
+    println!(" - a_count: {a_count}");
+    println!(" - b_count: {b_count}");
+    println!(" - diff: {diff}");
+
+    // rejection sampling removes the old rounding bias, so the counts should track
+    // pure binomial noise: for 10M coin flips a difference of 8,000 (0.08%) between
+    // totals is reasonable
+    assert!(diff < 8_000);
+
+    println!("done\ntesting pool of size 3 for rounding bias");
+
+    // a pool size that doesn't evenly divide 256 badly exposed the old
+    // float-scaling bias, which made the first/last elements ~half as likely
+    let pool = ['a', 'b', 'c'];
+    let mut counts = [0i32; 3];
+    for _ in 0..9_000_000 {
+        let c = gen_rand_char(&pool);
+        match c {
+            'a' => counts[0] += 1,
+            'b' => counts[1] += 1,
+            'c' => counts[2] += 1,
+            _ => unreachable!(),
+        }
+    }
+
+    println!(" - counts: {counts:?}");
+
+    let expected = 3_000_000;
+    for count in counts {
+        assert!((count - expected).abs() < 8_000);
+    }
+
+    println!("done");
+}
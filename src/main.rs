@@ -1,29 +1,12 @@
 #![feature(let_chains)]
 
 use cli_clipboard::{ClipboardContext, ClipboardProvider};
-use getrandom::getrandom;
+use rand_str_gen::{
+    generate_words, DEFAULT_AMBIGUOUS_CHARS, DEFAULT_WORDLIST, Generator, GetRandomSource,
+    PoolBuilder, RandSource, Wyrand,
+};
 use termion::{color::*, style};
 
-const DIGITS: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
-const LETTERS_LC: [char; 26] = [
-    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
-    't', 'u', 'v', 'w', 'x', 'y', 'z',
-];
-const LETTERS_UC: [char; 26] = [
-    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
-    'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
-];
-const SEPARATORS: [char; 3] = ['-', '.', '_'];
-const MISC_SYMBOLS: [char; 4] = ['!', '*', '&', '#'];
-
-const DEFINED_SETS: [&'static [char]; 5] = [
-    &DIGITS,
-    &LETTERS_LC,
-    &LETTERS_UC,
-    &SEPARATORS,
-    &MISC_SYMBOLS,
-];
-
 fn print_help() {
     println!(
         r#"
@@ -32,16 +15,30 @@ rand-str-gen [args] [len] [entries]
 len: the number of characters in the generated string, must be a positive integer
 
 args:
-  --help    -h : display this help dialog
-  --copy    -c : put the generated string in the OS clipboard
-  --repeat  -r : set number of strings to generate, must be a positive integer
+  --help     -h : display this help dialog
+  --copy     -c : put the generated string in the OS clipboard
+  --repeat   -r : set number of strings to generate, must be a positive integer
+  --entropy  -e : derive the length from a target entropy (bits) instead of
+                  an explicit length, e.g. `-e 128` for a 128-bit string
+  --strict      : guarantee at least one character from every enabled set
+                  (each pre-defined set still in the pool, and each custom
+                  `[...]` set that was added) appears in the output
+  --no-ambiguous         : remove visually ambiguous characters from the pool,
+                           default set: 0 O o 1 l I | 5 S 2 Z B 8
+  --ambiguous-chars <cs> : override the characters --no-ambiguous removes
+  --seed <value>         : use a seeded PRNG instead of the OS CSPRNG, so the
+                           same seed and args always produce the same output
+  --words     -w <n> : generate a passphrase of `n` random words instead of a
+                        random string, using a bundled wordlist
+  --wordlist <path>   : load a newline-separated custom wordlist for --words
+  --word-sep <char>   : separator between words, default '-'
 
 entries: [+|-][entry]
   +  adds entry to the character pool
   -  removes entry from the character pool
-  
+
   Entries are a sequence of pre-defined and custom sets (not seperated by white-space or commas).
-  
+
   Pre-defined sets:
      d : decimal digits, 0-9
      u : uppercase english alphabet, A-Z
@@ -49,15 +46,15 @@ entries: [+|-][entry]
      s : separators, ['-', '.', '_']
      m : misc symbols, ['!', '*', '&', '#']
      A : alias for all sets (dulsm)
-  
+
   Custom set: [characters]
     All UTF-8 characters between the '[' and the ']' are included in the set.
     If you want ']' in the set, too bad, because that denotes the end of the sequence and I don't feel like managing such case.
-    
+
     If specifying a custom set, you might have to put the argument into quotes.
-  
+
   By default, all pre-defined sets are added to the pool.
-  
+
 EXAMPLES:
 
 {0}// Generate random string of length 10{1}
@@ -72,6 +69,12 @@ rand-str-gen 10 -m "+[%$^@]"
 {0}// With default sets, but without '.'{1}
 rand-str-gen 10 "-[.]"
 
+{0}// Generate a string with at least 128 bits of entropy{1}
+rand-str-gen --entropy 128 --show-pool
+
+{0}// Generate a 6-word passphrase{1}
+rand-str-gen --words 6
+
 "#,
         Fg(LightBlack),
         style::Reset,
@@ -88,32 +91,23 @@ macro_rules! err {
     }};
 }
 
-fn gen_rand_string(pool: &[char], len: usize) -> String {
-    if len == 0 || pool.is_empty() {
-        return String::new();
-    }
-
-    let mut indices = vec![0; len];
-    getrandom(&mut indices).unwrap();
-
-    // indices are in range 0..256, they need to be mapped to 0..pool.len()
-
-    let scale = (pool.len() - 1) as f32 / 255.0;
-
-    indices
-        .into_iter()
-        .map(|idx| pool[(idx as f32 * scale).round() as usize])
-        .collect()
-}
-
 fn main() {
     let mut args = std::env::args();
     let _bin_path = args.next().unwrap();
 
     let mut show_pool = false;
     let mut copy_cond = false;
+    let mut strict = false;
+    let mut no_ambiguous = false;
+    let mut ambiguous_chars: Vec<char> = DEFAULT_AMBIGUOUS_CHARS.to_vec();
     let mut len: Option<usize> = None;
+    let mut entropy_bits: Option<f64> = None;
+    let mut seed: Option<u64> = None;
+    let mut words: Option<usize> = None;
+    let mut wordlist_path: Option<String> = None;
+    let mut word_sep: char = '-';
     let mut repeat = 1;
+    let mut pending_entry: Option<String> = None;
 
     // --- PARSE ARGS ---
     while let Some(arg) = args.next().clone() {
@@ -121,6 +115,47 @@ fn main() {
 
         match arg.as_str() {
             "--copy" | "-c" => copy_cond = true,
+            "--strict" => strict = true,
+            "--no-ambiguous" => no_ambiguous = true,
+            "--ambiguous-chars" => {
+                let Some(arg) = args.next() else {
+            		err!("expected arg: ambiguous characters", USE_HELP_MSG);
+            	};
+                ambiguous_chars = arg.chars().collect();
+            }
+            "--seed" => {
+                let Some(arg) = args.next() else {
+            		err!("expected arg: seed", USE_HELP_MSG);
+            	};
+                seed = match arg.parse() {
+                    Ok(seed) => Some(seed),
+                    Err(e) => err!(&format!("invalid seed: {e}"), USE_HELP_MSG),
+                };
+            }
+            "--words" | "-w" => {
+                let Some(arg) = args.next() else {
+            		err!("expected arg: number of words", USE_HELP_MSG);
+            	};
+                words = match arg.parse() {
+                    Ok(n) => Some(n),
+                    Err(e) => err!(&format!("invalid word count: {e}"), USE_HELP_MSG),
+                };
+            }
+            "--wordlist" => {
+                let Some(arg) = args.next() else {
+            		err!("expected arg: wordlist path", USE_HELP_MSG);
+            	};
+                wordlist_path = Some(arg);
+            }
+            "--word-sep" => {
+                let Some(arg) = args.next() else {
+            		err!("expected arg: word separator", USE_HELP_MSG);
+            	};
+                word_sep = match arg.chars().next() {
+                    Some(c) => c,
+                    None => err!("word separator can't be empty", USE_HELP_MSG),
+                };
+            }
             "--repeat" | "-r" => {
                 let Some(arg) = args.next() else {
             		err!("expected arg: repeat count", USE_HELP_MSG);
@@ -130,6 +165,16 @@ fn main() {
                     Err(e) => err!(&format!("invalid count: {e}"), USE_HELP_MSG),
                 };
             }
+            "--entropy" | "-e" => {
+                let Some(arg) = args.next() else {
+            		err!("expected arg: target entropy in bits", USE_HELP_MSG);
+            	};
+                entropy_bits = match arg.parse::<f64>() {
+                    Ok(bits) if bits > 0.0 => Some(bits),
+                    Ok(_) => err!("entropy must be a positive number of bits", USE_HELP_MSG),
+                    Err(e) => err!(&format!("invalid entropy: {e}"), USE_HELP_MSG),
+                };
+            }
             "--help" | "-h" => {
                 print_help();
                 return;
@@ -137,13 +182,19 @@ fn main() {
             "--show-pool" => show_pool = true,
             _ => matches = false,
         };
-        if arg.starts_with("-") && !matches {
-            err!(&format!("invalid arg: '{}'", arg), USE_HELP_MSG);
-        }
         if matches {
             continue;
         }
-        // arg doesn't start with -, so it should be the len arg
+        // arg doesn't start with a recognized flag, so it's either the len arg
+        // or, with `--entropy` and no explicit len, the start of the entries
+
+        if (arg.starts_with('+') || arg.starts_with('-')) && entropy_bits.is_some() {
+            pending_entry = Some(arg);
+            break;
+        }
+        if arg.starts_with("-") {
+            err!(&format!("invalid arg: '{}'", arg), USE_HELP_MSG);
+        }
 
         len = match arg.parse() {
             Ok(len) => Some(len),
@@ -152,16 +203,74 @@ fn main() {
         break;
     }
 
-    let Some(len) = len else {
-    	err!("expected arg: length of password", USE_HELP_MSG)	
-    };
+    // --- WORDS (PASSPHRASE) MODE ---
+    if let Some(n_words) = words {
+        if len.is_some() || entropy_bits.is_some() {
+            err!(
+                "can't use --words together with an explicit length or --entropy",
+                USE_HELP_MSG
+            );
+        }
+
+        let wordlist_text = match &wordlist_path {
+            Some(path) => match std::fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(e) => err!(&format!("failed to read wordlist '{path}': {e}"), USE_HELP_MSG),
+            },
+            None => DEFAULT_WORDLIST.to_string(),
+        };
+        let word_pool: Vec<&str> = wordlist_text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        if word_pool.len() < 2 {
+            err!("wordlist must have at least 2 words", USE_HELP_MSG);
+        }
+
+        if show_pool {
+            println!("wordlist: {} words", word_pool.len());
+            let achieved_bits = n_words as f64 * (word_pool.len() as f64).log2();
+            println!("entropy: {achieved_bits:.2} bits");
+        }
+
+        let mut source: Box<dyn RandSource> = match seed {
+            Some(seed) => Box::new(Wyrand::new(seed)),
+            None => Box::new(GetRandomSource),
+        };
 
-    let mut use_defined_sets = [true; DEFINED_SETS.len()];
-    let mut add_chars = Vec::new();
-    let mut remove_chars = Vec::new();
+        let strings: Vec<_> = (0..repeat)
+            .map(|_| generate_words(&word_pool, n_words, word_sep, source.as_mut()))
+            .collect();
+
+        for string in &strings {
+            println!("{}", string);
+        }
+
+        if let Some(string) = strings.last() && copy_cond {
+            let mut cb = ClipboardContext::new().expect("failed to create OS clipboard context");
+            cb.set_contents(string.clone())
+                .expect("failed to set OS clipboard contents");
+        }
+
+        return;
+    }
+
+    if len.is_none() && entropy_bits.is_none() {
+        err!("expected arg: length of password", USE_HELP_MSG);
+    }
+    if len.is_some() && entropy_bits.is_some() {
+        err!(
+            "can't use both an explicit length and --entropy",
+            USE_HELP_MSG
+        );
+    }
+
+    let mut pool_builder = PoolBuilder::new();
 
     // --- PARSE POOL MODIFIERS (ENTRIES) ---
-    while let Some(arg) = args.next() {
+    while let Some(arg) = pending_entry.take().or_else(|| args.next()) {
         if arg.is_empty() {
             continue;
         }
@@ -177,12 +286,12 @@ fn main() {
 
         while let Some(set) = chars.next() {
             match set {
-                'd' => use_defined_sets[0] = state,
-                'l' => use_defined_sets[1] = state,
-                'u' => use_defined_sets[2] = state,
-                's' => use_defined_sets[3] = state,
-                'm' => use_defined_sets[4] = state,
-                'A' => use_defined_sets = [false; DEFINED_SETS.len()],
+                'd' => pool_builder.use_defined_set(0, state),
+                'l' => pool_builder.use_defined_set(1, state),
+                'u' => pool_builder.use_defined_set(2, state),
+                's' => pool_builder.use_defined_set(3, state),
+                'm' => pool_builder.use_defined_set(4, state),
+                'A' => pool_builder.use_all_defined_sets(state),
                 '[' => {
                     let mut set_chars = Vec::new();
                     while let Some(c) = chars.next() {
@@ -192,10 +301,11 @@ fn main() {
                         set_chars.push(c);
                     }
                     if state {
-                        add_chars.extend(set_chars);
+                        pool_builder.add_custom_set(set_chars);
                     } else {
-                        remove_chars.extend(set_chars);
+                        pool_builder.remove_chars(set_chars);
                     }
+                    continue;
                 }
                 e => err!(
                     &format!("invalid pool entry: '{e}'"),
@@ -205,114 +315,76 @@ fn main() {
         }
     }
 
-    // --- CREATE POOL ---
-    let mut pool = Vec::new();
-    for i in 0..DEFINED_SETS.len() {
-        if use_defined_sets[i] {
-            pool.extend(DEFINED_SETS[i]);
-        }
-    }
-    for c in add_chars {
-        if pool.iter().position(|c2| *c2 == c).is_some() {
-            err!(
-                &format!("can't add character to pool, already exists: '{}'", c),
-                &format!("characters in the set are: {pool:?}")
-            )
-        }
-        pool.push(c);
-    }
-    for c in remove_chars {
-        let Some(idx) = pool.iter().position(|c2| *c2 == c) else {
-    		err!(
-    			&format!("can't remove character from pool, doesn't exist: '{}'", c),
-    			&format!("characters in the set are: {pool:?}")
-    		)
-    	};
-        pool.remove(idx);
+    if no_ambiguous {
+        pool_builder.filter_ambiguous(ambiguous_chars.iter().copied());
     }
 
+    let pool = match pool_builder.build() {
+        Ok(pool) => pool,
+        Err(e) => err!(&e, USE_HELP_MSG),
+    };
+
     if show_pool {
         println!("pool: {pool:?}");
     }
 
-    // --- CREATE STRING ---
-
-    let strings: Vec<_> = (0..repeat).map(|_| gen_rand_string(&pool, len)).collect();
+    // --- REQUIRED SETS (for --strict) ---
 
-    for string in &strings {
-        println!("{}", string);
-    }
-
-    if let Some(string) = strings.last() && copy_cond {
-        let mut cb = ClipboardContext::new().expect("failed to create OS clipboard context");
-        cb.set_contents(string.clone())
-            .expect("failed to set OS clipboard contents");
-    }
-}
-
-// ---- UNIT TESTS ----
-
-#[cfg(test)]
-#[inline(always)]
-fn gen_rand_char(pool: &[char]) -> char {
-    // the unit test shouldn't give this an empty pool
-    assert_eq!(pool.is_empty(), false);
+    let required_sets: Vec<Vec<char>> = if strict {
+        match pool_builder.build_required_sets() {
+            Ok(sets) => sets,
+            Err(e) => err!(
+                &e,
+                "expand the set, choose different --ambiguous-chars, or drop --strict"
+            ),
+        }
+    } else {
+        Vec::new()
+    };
 
-    let mut index = [0];
-    getrandom(&mut index).unwrap();
+    // --- RESOLVE LENGTH ---
 
-    let scale = (pool.len() - 1) as f32 / 255.0;
-    pool[(index[0] as f32 * scale).round() as usize]
-}
+    let len = match entropy_bits {
+        Some(bits) => {
+            if pool.len() < 2 {
+                err!(
+                    "pool must have at least 2 characters to target an entropy, has zero entropy",
+                    "add more characters to the pool"
+                );
+            }
+            let per_char_bits = (pool.len() as f64).log2();
+            (bits / per_char_bits).ceil() as usize
+        }
+        None => len.unwrap(),
+    };
 
-#[test]
-fn test() {
-    // --- SMALLER TESTS ---
-    assert_eq!(gen_rand_string(&[], 5).as_str(), "");
-    assert_eq!(gen_rand_string(&[], 0).as_str(), "");
-    assert_eq!(gen_rand_string(&['a'], 0).as_str(), "");
-    for _ in 0..100 {
-        assert_eq!(gen_rand_string(&['a'], 1).as_str(), "a");
+    if show_pool {
+        let achieved_bits = len as f64 * (pool.len() as f64).log2();
+        println!("entropy: {achieved_bits:.2} bits");
     }
 
-    // --- BIGGER TESTS ---
-    println!("\ntesting pool of size 1");
-
-    let pool = ['a'];
-    for _ in 0..10_000_000 {
-        // this asserts that get_rand_char doesn't calculate an invalid index or char
-        let c = gen_rand_char(&pool);
-        assert_eq!(c, 'a');
-    }
-    println!("done\ntesting pool of size 2");
+    // --- CREATE STRING ---
 
-    let pool = ['a', 'b'];
-    for _ in 0..10_000_000 {
-        let c = gen_rand_char(&pool);
-        assert!(c == 'a' || c == 'b');
+    let mut generator = Generator::new(pool, len);
+    if strict {
+        generator = match generator.with_strict(required_sets) {
+            Ok(generator) => generator,
+            Err(e) => err!(&e, "increase the length, or drop --strict"),
+        };
     }
-
-    println!("done\nchecking distrobution");
-
-    // assert that the distrobutions are ~even
-    let mut a_count: i32 = 0;
-    let mut b_count: i32 = 0;
-    for _ in 0..10_000_000 {
-        let c = gen_rand_char(&pool);
-        match c {
-            'a' => a_count += 1,
-            'b' => b_count += 1,
-            _ => unreachable!(),
-        }
+    if let Some(seed) = seed {
+        generator = generator.with_seed(seed);
     }
-    let diff = (a_count - b_count).abs();
 
-    println!(" - a_count: {a_count}");
-    println!(" - b_count: {b_count}");
-    println!(" - diff: {diff}");
+    let strings = generator.generate_many(repeat);
 
-    // for 10M coin flips, I'd say a difference of 10,000 (0.01%) between totals is reasonable
-    assert!(diff < 10_000);
+    for string in &strings {
+        println!("{}", string);
+    }
 
-    println!("done");
+    if let Some(string) = strings.last() && copy_cond {
+        let mut cb = ClipboardContext::new().expect("failed to create OS clipboard context");
+        cb.set_contents(string.clone())
+            .expect("failed to set OS clipboard contents");
+    }
 }